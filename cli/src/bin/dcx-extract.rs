@@ -1,20 +1,147 @@
-use std::io::{Cursor, Write, Read};
+use std::borrow::Cow;
+use std::io::{Cursor, Read, Write};
 
-use clap::Parser;
-use memmap2::{Advice, Mmap, MmapOptions};
+use clap::{Parser, Subcommand};
+use memmap2::{Advice, MmapOptions};
+use rayon::prelude::*;
 
-use format::{bnd4::BND4, dcx::Dcx};
+use format::{
+    bnd4::BND4,
+    dcx::{Dcx, DcxMethod},
+    tpf::Tpf,
+};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    #[arg(long)]
-    file: String,
+    #[command(subcommand)]
+    command: Command,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Decompress a `.dcx` and write every BND4 entry to disk.
+    Extract {
+        #[arg(long)]
+        file: String,
+        /// Also decode `.tpf` entries to browsable PNGs alongside the raw extracted files.
+        #[arg(long)]
+        extract_textures: bool,
+        /// Number of entries to decode/write concurrently. Defaults to the available parallelism.
+        #[arg(long)]
+        jobs: Option<usize>,
+    },
+    /// Rebuild a `.dcx` from a folder previously produced by `extract`.
+    Repack {
+        #[arg(long)]
+        folder: String,
+        #[arg(long)]
+        out: String,
+    },
+    /// Validate a `.dcx`'s block table and its BND4's entry table, reporting every anomaly found.
+    Check {
+        #[arg(long)]
+        file: String,
+    },
+    /// Like `check`, but also write out a salvaged copy with corrupt entries dropped/truncated.
+    Repair {
+        #[arg(long)]
+        file: String,
+        #[arg(long)]
+        out: String,
+    },
+}
+
+/// Name of the per-folder manifest `extract` writes and `repack` reads back, recording the
+/// original entry order/IDs so a repacked archive stays byte-compatible with the game's loader.
+const MANIFEST_NAME: &str = "_bnd4_manifest.tsv";
+
 fn main() -> Result<(), std::io::Error> {
     let args = Args::parse();
-    let path = std::path::PathBuf::from(args.file);
+
+    match args.command {
+        Command::Extract { file, extract_textures, jobs } => extract(file, extract_textures, jobs),
+        Command::Repack { folder, out } => repack(folder, out),
+        Command::Check { file } => check(file),
+        Command::Repair { file, out } => repair(file, out),
+    }
+}
+
+/// Parse `file` as far as possible, printing every anomaly found along the way.
+///
+/// Returns the decompressed BND4 buffer and parsed table when parsing got far enough to produce
+/// them, so `repair` can reuse this without re-running the whole walk.
+fn run_checks(file: &str) -> Result<Option<(Vec<u8>, BND4)>, std::io::Error> {
+    let data = std::fs::read(file)?;
+
+    let dcx = match Dcx::parse(&data) {
+        Ok(dcx) => dcx,
+        Err(e) => {
+            println!("offset 0x0: failed to parse DCX header: {e}");
+            return Ok(None);
+        }
+    };
+
+    for anomaly in dcx.check() {
+        println!("offset {:#x}: {}", anomaly.offset, anomaly.message);
+    }
+
+    let mut decoder = match dcx.create_decoder() {
+        Ok(decoder) => decoder,
+        Err(e) => {
+            println!("offset 0x0: could not create decoder: {e}");
+            return Ok(None);
+        }
+    };
+
+    let mut decompressed = Vec::with_capacity(dcx.hint_size());
+    if let Err(e) = decoder.read_to_end(&mut decompressed) {
+        println!("offset 0x0: failed to decode DCX payload: {e}");
+        return Ok(None);
+    }
+
+    let bnd4 = match BND4::from_reader(&mut Cursor::new(&decompressed)) {
+        Ok(bnd4) => bnd4,
+        Err(e) => {
+            println!("offset 0x0: failed to parse BND4 header/table: {e}");
+            return Ok(None);
+        }
+    };
+
+    for anomaly in bnd4.check(decompressed.len()) {
+        println!("offset {:#x}: {}", anomaly.offset, anomaly.message);
+    }
+
+    Ok(Some((decompressed, bnd4)))
+}
+
+fn check(file: String) -> Result<(), std::io::Error> {
+    run_checks(&file)?;
+    Ok(())
+}
+
+fn repair(file: String, out: String) -> Result<(), std::io::Error> {
+    let Some((decompressed, bnd4)) = run_checks(&file)? else {
+        println!("archive is too corrupted to repair (could not even parse the BND4 table)");
+        return Ok(());
+    };
+
+    let (repaired, contents) = bnd4.repair(&decompressed);
+
+    let mut new_decompressed = Cursor::new(Vec::new());
+    repaired.to_writer(&mut new_decompressed, &contents)?;
+
+    // `run_checks` already confirmed the original parses as a DCX, so re-read just its method and
+    // re-wrap with the same one rather than assuming Kraken.
+    let method = Dcx::parse(&std::fs::read(&file)?)?.method();
+    let dcx = Dcx::encode(new_decompressed.get_ref(), method)?;
+    std::fs::write(out, dcx)?;
+
+    Ok(())
+}
+
+fn extract(file: String, extract_textures: bool, jobs: Option<usize>) -> Result<(), std::io::Error> {
+    let path = std::path::PathBuf::from(file);
 
     let dcx_file = std::fs::File::open(&path)?;
     let data = unsafe {
@@ -22,36 +149,126 @@ fn main() -> Result<(), std::io::Error> {
             .populate()
             .map_copy_read_only(&dcx_file)?
     };
+    // We read the whole mapping once, start to finish, to feed the decompressor, then never touch
+    // it again: tell the kernel to read ahead aggressively rather than cache it page-by-page.
+    data.advise(Advice::Sequential)?;
+    data.advise(Advice::WillNeed)?;
 
     let dcx = Dcx::parse(&data).unwrap();
 
     let mut decoder = dcx.create_decoder()
         .expect("Could not create decoder");
 
-    let mut decompressed = Vec::with_capacity(decoder.hint_size());
+    let mut decompressed = Vec::with_capacity(dcx.hint_size());
     decoder.read_to_end(&mut decompressed)?;
 
-    let mut cursor = std::io::Cursor::new(decompressed);
-    let bnd4 = BND4::from_reader(&mut cursor)?;
+    let bnd4 = BND4::from_reader(&mut Cursor::new(&decompressed))?;
 
     let folder = format!(
         "{}/{}/",
         path.parent().unwrap().to_str().unwrap(),
         path.file_stem().unwrap().to_str().unwrap(),
     );
+    std::fs::create_dir_all(&folder)?;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.unwrap_or(0))
+        .build()
+        .map_err(std::io::Error::other)?;
+
+    let manifest_entries: Vec<(i32, String)> = pool.install(|| {
+        bnd4.files
+            .par_iter()
+            .map(|entry| -> Result<(i32, String), std::io::Error> {
+                let trimmed_path = entry.path.replace("N:\\", "").replace('\\', "/");
+                let output_path = std::path::PathBuf::from(folder.clone()).join(trimmed_path.as_str());
 
-    for entry in bnd4.files.iter() {
-        let trimmed_path = entry.path.replace("N:\\", "").replace('\\', "/");
-        let output_path = std::path::PathBuf::from(folder.clone()).join(trimmed_path.as_str());
+                // `create_dir_all` is safe to call concurrently for overlapping paths: it no-ops
+                // on an `AlreadyExists` for a directory, which is the only race two entries
+                // writing into the same folder can hit.
+                let parent = output_path.parent().unwrap();
+                std::fs::create_dir_all(parent)?;
 
-        let parent = output_path.parent().unwrap();
-        std::fs::create_dir_all(parent)?;
+                let bytes = entry.bytes(&decompressed)?;
+                let bytes = decode_if_dcx(bytes)?;
+                std::fs::write(&output_path, bytes.as_ref())?;
 
-        let bytes = entry.bytes(&mut cursor)?;
+                if extract_textures && is_tpf(&bytes, &output_path) {
+                    extract_textures_from(&bytes, &output_path)?;
+                }
 
-        let mut file = std::fs::File::create(&output_path)?;
-        file.write_all(&bytes)?;
+                Ok((entry.id, trimmed_path))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+
+    let mut manifest = std::fs::File::create(std::path::PathBuf::from(&folder).join(MANIFEST_NAME))?;
+    writeln!(manifest, "{}", dcx.method().name())?;
+    for (id, path) in manifest_entries {
+        writeln!(manifest, "{id}\t{path}")?;
     }
 
     Ok(())
 }
+
+/// If `bytes` is itself individually DCX-compressed (some BND4 entries are, independent of the
+/// archive's own compression), decode it; otherwise return it unchanged.
+fn decode_if_dcx(bytes: &[u8]) -> Result<Cow<[u8]>, std::io::Error> {
+    if bytes.len() < 4 || &bytes[0..4] != b"DCX\0" {
+        return Ok(Cow::Borrowed(bytes));
+    }
+    let dcx = Dcx::parse(bytes)?;
+    let mut decoder = dcx.create_decoder()?;
+    let mut decoded = Vec::with_capacity(dcx.hint_size());
+    decoder.read_to_end(&mut decoded)?;
+    Ok(Cow::Owned(decoded))
+}
+
+fn is_tpf(bytes: &[u8], output_path: &std::path::Path) -> bool {
+    output_path.extension().and_then(|e| e.to_str()) == Some("tpf")
+        || (bytes.len() >= 4 && &bytes[0..4] == b"TPF\0")
+}
+
+fn extract_textures_from(bytes: &[u8], output_path: &std::path::Path) -> Result<(), std::io::Error> {
+    // `extract` already ran this entry through `decode_if_dcx`, so `bytes` is the plain TPF table.
+    let Ok(tpf) = Tpf::parse(bytes) else {
+        return Ok(());
+    };
+
+    tpf.textures.par_iter().try_for_each(|texture| -> Result<(), std::io::Error> {
+        if let Some(png) = texture.to_png() {
+            std::fs::write(output_path.with_extension(format!("{}.png", texture.name)), png)?;
+        }
+        Ok(())
+    })
+}
+
+fn repack(folder: String, out: String) -> Result<(), std::io::Error> {
+    let folder = std::path::PathBuf::from(folder);
+    let manifest = std::fs::read_to_string(folder.join(MANIFEST_NAME))?;
+
+    let mut lines = manifest.lines();
+    let method = DcxMethod::from_name(lines.next().unwrap_or_default())?;
+
+    let entries: Vec<(i32, String)> = lines
+        .filter_map(|line| {
+            let (id, path) = line.split_once('\t')?;
+            Some((id.parse().ok()?, path.to_string()))
+        })
+        .collect();
+
+    let contents: Vec<Vec<u8>> = entries
+        .par_iter()
+        .map(|(_, path)| std::fs::read(folder.join(path)))
+        .collect::<Result<_, _>>()?;
+
+    let bnd4 = BND4::from_entries(entries);
+
+    let mut decompressed = Cursor::new(Vec::new());
+    bnd4.to_writer(&mut decompressed, &contents)?;
+
+    let dcx = Dcx::encode(decompressed.get_ref(), method)?;
+    std::fs::write(out, dcx)?;
+
+    Ok(())
+}
@@ -0,0 +1,547 @@
+//! BND4: FromSoftware's general-purpose indexed archive format.
+//!
+//! A BND4 is a small header, a path/flags/offset table (one entry per file), and the file bytes
+//! themselves. [`BND4::from_reader`] parses the table against an already-decompressed buffer (the
+//! caller typically gets this from [`crate::dcx::Dcx::create_decoder`]); [`BND4::to_writer`]
+//! rebuilds a byte-compatible container from a (possibly edited) set of entries.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use byteorder::{WriteBytesExt, LE};
+
+use crate::io_ext::{Endian, ReadFormatsExt, WriteFormatsExt};
+
+/// One file entry's table row, plus enough bookkeeping to read its bytes back out on demand.
+#[derive(Debug, Clone)]
+pub struct BND4Entry {
+    pub id: i32,
+    pub path: String,
+    flags: u8,
+    data_offset: u64,
+    size: usize,
+}
+
+impl BND4Entry {
+    /// Borrow this entry's file bytes out of the buffer `from_reader` parsed.
+    ///
+    /// `data` must be the same buffer (or an identical copy) `BND4::from_reader` was given; this
+    /// is a plain slice rather than a `Read + Seek`, so independent entries can be sliced out and
+    /// processed concurrently without any shared mutable cursor.
+    pub fn bytes<'a>(&self, data: &'a [u8]) -> io::Result<&'a [u8]> {
+        let start = self.data_offset as usize;
+        data.get(start..start + self.size)
+            .ok_or_else(|| io::Error::other("BND4 entry data out of bounds"))
+    }
+}
+
+/// Alignment FromSoftware's tools pad each entry's data to; required for a rebuilt archive to be
+/// byte-compatible with the game's own loader, which assumes this spacing.
+const DATA_ALIGNMENT: u64 = 0x10;
+
+/// One row of a `extended == 4` container's bucket hash table: a file's path hash, paired with
+/// its index into [`BND4::files`]. The game's loader uses this to find a file by path without a
+/// linear scan of the entry table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HashEntry {
+    hash: u32,
+    file_index: i32,
+}
+
+/// FromSoftware's path hash: lowercase the path, normalize backslashes to forward slashes, then
+/// fold it through a multiply-by-37 hash.
+fn path_hash(path: &str) -> u32 {
+    path.to_lowercase()
+        .replace('\\', "/")
+        .bytes()
+        .fold(0u32, |hash, b| hash.wrapping_mul(37).wrapping_add(b as u32))
+}
+
+/// Bucket count FromSoftware's tools use for a given file count: the smallest power of two not
+/// less than the file count, which keeps buckets close to one entry deep on average.
+fn bucket_count_for(file_count: usize) -> u32 {
+    (file_count.max(1) as u32).next_power_of_two()
+}
+
+/// Recompute a fresh, internally-consistent bucket hash table for `files`, with its entries
+/// grouped by bucket rather than in file-table order.
+fn build_hash_table(files: &[BND4Entry]) -> (u32, Vec<(u32, u32)>, Vec<HashEntry>) {
+    let bucket_count = bucket_count_for(files.len());
+
+    let mut entries: Vec<HashEntry> = files
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| HashEntry { hash: path_hash(&entry.path), file_index: i as i32 })
+        .collect();
+    // Grouping by bucket (and sorting each bucket's entries by hash) lets the loader binary-search
+    // within a bucket instead of scanning it linearly.
+    entries.sort_by_key(|e| (e.hash % bucket_count, e.hash));
+
+    let mut buckets = vec![(0u32, 0u32); bucket_count as usize];
+    for (i, entry) in entries.iter().enumerate() {
+        let bucket = (entry.hash % bucket_count) as usize;
+        if buckets[bucket].0 == 0 {
+            buckets[bucket].1 = i as u32;
+        }
+        buckets[bucket].0 += 1;
+    }
+
+    (bucket_count, buckets, entries)
+}
+
+/// Read the bucket hash table at the reader's current position: a 16-byte header (bucket count,
+/// hash entry count, absolute offset to the hash entry array), immediately followed by the bucket
+/// array, with the hash entry array living wherever the header pointed.
+fn read_hash_table<R: Read + Seek>(reader: &mut R) -> io::Result<Vec<HashEntry>> {
+    let bucket_count = reader.read_u32(Endian::Little)? as usize;
+    let hash_entry_count = reader.read_u32(Endian::Little)? as usize;
+    let hash_entries_offset = reader.read_u64(Endian::Little)?;
+
+    // The bucket array (count/index pairs) immediately follows the header; it is skipped, not
+    // read or validated, since the header already gives the hash entries' absolute offset.
+    reader.seek(SeekFrom::Current(bucket_count as i64 * 8))?;
+
+    reader.seek(SeekFrom::Start(hash_entries_offset))?;
+    let mut entries = Vec::with_capacity(hash_entry_count);
+    for _ in 0..hash_entry_count {
+        let hash = reader.read_u32(Endian::Little)?;
+        let file_index = reader.read_i32(Endian::Little)?;
+        entries.push(HashEntry { hash, file_index });
+    }
+
+    Ok(entries)
+}
+
+/// Write a freshly-computed bucket hash table for `files` at the writer's current position,
+/// matching the layout [`read_hash_table`] expects.
+fn write_hash_table<W: Write + Seek>(writer: &mut W, files: &[BND4Entry]) -> io::Result<()> {
+    let (bucket_count, buckets, entries) = build_hash_table(files);
+
+    writer.write_u32::<LE>(bucket_count)?;
+    writer.write_u32::<LE>(entries.len() as u32)?;
+    let hash_entries_offset_pos = writer.stream_position()?;
+    writer.write_u64::<LE>(0)?; // hash_entries_offset, patched below
+
+    for (count, index) in &buckets {
+        writer.write_u32::<LE>(*count)?;
+        writer.write_u32::<LE>(*index)?;
+    }
+
+    let hash_entries_offset = writer.stream_position()?;
+    for entry in &entries {
+        writer.write_u32::<LE>(entry.hash)?;
+        writer.write_i32::<LE>(entry.file_index)?;
+    }
+
+    let end_pos = writer.stream_position()?;
+    writer.seek(SeekFrom::Start(hash_entries_offset_pos))?;
+    writer.write_u64::<LE>(hash_entries_offset)?;
+    writer.seek(SeekFrom::Start(end_pos))?;
+
+    Ok(())
+}
+
+/// A single validation finding from [`BND4::check`], anchored to the byte offset it was found at.
+#[derive(Debug, Clone)]
+pub struct Anomaly {
+    pub offset: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct BND4 {
+    pub files: Vec<BND4Entry>,
+    unicode: bool,
+    raw_format: u8,
+    extended: u8,
+    /// The bucket hash table read from the source archive, for `extended == 4` containers;
+    /// `None` otherwise. Only used by [`BND4::check`] to validate itself against `files` —
+    /// [`BND4::to_writer`] always recomputes a fresh table from `files` rather than trusting this
+    /// one, so edits made through [`BND4::repair`] (or a hand-edited `files`) can't leave it stale.
+    hash_table: Option<Vec<HashEntry>>,
+}
+
+impl BND4 {
+    /// Build a fresh BND4 from `(id, path)` pairs, in the order they should appear on disk.
+    ///
+    /// Used by the `repack` CLI subcommand, which only has a manifest of IDs/paths (written out
+    /// by `extract`) rather than a full parsed [`BND4`] to round-trip.
+    pub fn from_entries(entries: Vec<(i32, String)>) -> Self {
+        BND4 {
+            files: entries
+                .into_iter()
+                .map(|(id, path)| BND4Entry { id, path, flags: 0x40, data_offset: 0, size: 0 })
+                .collect(),
+            unicode: true,
+            raw_format: 0x74,
+            extended: 0,
+            hash_table: None,
+        }
+    }
+
+    /// Confirm every entry's `offset + size` lies within `buffer_len` and its path resolved to
+    /// something, reporting each problem instead of panicking.
+    pub fn check(&self, buffer_len: usize) -> Vec<Anomaly> {
+        let mut anomalies = Vec::new();
+
+        for (i, entry) in self.files.iter().enumerate() {
+            let end = entry.data_offset.saturating_add(entry.size as u64);
+            if end as usize > buffer_len {
+                anomalies.push(Anomaly {
+                    offset: entry.data_offset,
+                    message: format!(
+                        "entry {i} ({:?}) data range {:#x}..{:#x} exceeds decompressed buffer length {:#x}",
+                        entry.path, entry.data_offset, end, buffer_len
+                    ),
+                });
+            }
+            if entry.path.is_empty() {
+                anomalies.push(Anomaly {
+                    offset: entry.data_offset,
+                    message: format!("entry {i} has an empty or unresolved name-table path"),
+                });
+            }
+        }
+
+        if let Some(hash_table) = &self.hash_table {
+            if hash_table.len() != self.files.len() {
+                anomalies.push(Anomaly {
+                    offset: 0,
+                    message: format!(
+                        "hash table has {} entries, but the archive has {} files",
+                        hash_table.len(),
+                        self.files.len()
+                    ),
+                });
+            }
+
+            for entry in hash_table {
+                match self.files.get(entry.file_index as usize) {
+                    Some(file) => {
+                        let expected = path_hash(&file.path);
+                        if expected != entry.hash {
+                            anomalies.push(Anomaly {
+                                offset: 0,
+                                message: format!(
+                                    "hash table entry for file {} ({:?}) has hash {:#010x}, expected {:#010x}",
+                                    entry.file_index, file.path, entry.hash, expected
+                                ),
+                            });
+                        }
+                    }
+                    None => anomalies.push(Anomaly {
+                        offset: 0,
+                        message: format!(
+                            "hash table entry references file index {}, but the archive only has {} files",
+                            entry.file_index,
+                            self.files.len()
+                        ),
+                    }),
+                }
+            }
+        }
+
+        anomalies
+    }
+
+    /// Build a salvageable copy of this archive: entries [`check`](Self::check) would flag for an
+    /// unresolved path are dropped, entries whose range runs past `data` are truncated to
+    /// whatever bytes are actually available, and entries starting past the end of `data` are
+    /// dropped. Returns the repaired table alongside the matching
+    /// content buffers, ready for [`BND4::to_writer`].
+    pub fn repair(&self, data: &[u8]) -> (BND4, Vec<Vec<u8>>) {
+        let mut files = Vec::new();
+        let mut contents = Vec::new();
+
+        for entry in &self.files {
+            if entry.path.is_empty() {
+                continue;
+            }
+
+            let start = entry.data_offset as usize;
+            if start > data.len() {
+                continue;
+            }
+
+            let available = data.len() - start;
+            let size = entry.size.min(available);
+            contents.push(data[start..start + size].to_vec());
+            files.push(BND4Entry { size, ..entry.clone() });
+        }
+
+        // Dropped/truncated entries shift file indices and invalidate path hashes, so a hash
+        // table carried over from `self` would immediately fail `check()` against the repaired
+        // file list; rebuild one from scratch instead, matching what `to_writer` would write.
+        let hash_table = if self.extended == 4 { Some(build_hash_table(&files).2) } else { None };
+
+        (BND4 { files, hash_table, ..self.clone() }, contents)
+    }
+
+    pub fn from_reader<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
+        reader.read_magic(b"BND4")?;
+
+        let _unk04 = reader.read_u8()?;
+        let _unk05 = reader.read_u8()?;
+        let _big_endian = reader.read_u8()?;
+        let _unk07 = reader.read_u8()?;
+        let _unk08 = reader.read_u8()?;
+        reader.seek(SeekFrom::Current(3))?;
+
+        let file_count = reader.read_u32(Endian::Little)? as usize;
+        let _header_size = reader.read_u64(Endian::Little)?;
+        let _version = {
+            let mut buf = [0u8; 8];
+            reader.read_exact(&mut buf)?;
+            buf
+        };
+        let file_header_size = reader.read_u64(Endian::Little)?;
+        let _file_header_offset = reader.read_u64(Endian::Little)?;
+        let unicode = reader.read_u8()? != 0;
+        let raw_format = reader.read_u8()?;
+        let extended = reader.read_u8()?;
+        reader.seek(SeekFrom::Current(5))?;
+
+        let hash_table_offset = if extended == 4 {
+            Some(reader.read_u64(Endian::Little)?)
+        } else {
+            None
+        };
+
+        let mut files = Vec::with_capacity(file_count);
+        for _ in 0..file_count {
+            // Entries are `file_header_size` bytes apart; we only read the fixed, known-layout
+            // prefix of each row, so seek to the declared stride rather than assuming it's exactly
+            // as wide as what we read (it's wider for `extended == 4` containers).
+            let entry_start = reader.stream_position()?;
+
+            let flags = reader.read_u8()?;
+            reader.seek(SeekFrom::Current(3))?;
+            let _breakable_size = reader.read_i32(Endian::Little)?;
+            let data_offset = reader.read_u64(Endian::Little)?;
+            let id = reader.read_i32(Endian::Little)?;
+            let name_offset = reader.read_i32(Endian::Little)? as u64;
+            let size = reader.read_u64(Endian::Little)? as usize;
+
+            let path = if name_offset == 0 {
+                String::new()
+            } else {
+                let return_pos = reader.stream_position()?;
+                reader.seek(SeekFrom::Start(name_offset))?;
+                let path = if unicode {
+                    reader.read_utf16_nul_terminated()?
+                } else {
+                    reader.read_shift_jis_nul_terminated()?
+                };
+                reader.seek(SeekFrom::Start(return_pos))?;
+                path
+            };
+
+            files.push(BND4Entry { id, path, flags, data_offset, size });
+
+            reader.seek(SeekFrom::Start(entry_start + file_header_size))?;
+        }
+
+        let hash_table = match hash_table_offset {
+            Some(offset) => {
+                reader.seek(SeekFrom::Start(offset))?;
+                Some(read_hash_table(reader)?)
+            }
+            None => None,
+        };
+
+        Ok(BND4 { files, unicode, raw_format, extended, hash_table })
+    }
+
+    /// Rebuild a byte-compatible BND4, substituting `contents[i]` for the bytes of `self.files[i]`.
+    ///
+    /// `contents` must be the same length as `self.files`, in the same order; entry IDs and paths
+    /// are taken from `self.files` unchanged, so callers only need to replace file bytes, not the
+    /// entry table itself.
+    pub fn to_writer<W: Write + Seek>(&self, writer: &mut W, contents: &[Vec<u8>]) -> io::Result<()> {
+        assert_eq!(contents.len(), self.files.len(), "one content buffer per entry is required");
+
+        writer.write_all(b"BND4")?;
+        writer.write_u8(1)?; // unk04
+        writer.write_u8(if self.files.is_empty() { 0 } else { 1 })?; // unk05
+        writer.write_u8(0)?; // big_endian
+        writer.write_u8(1)?; // unk07
+        writer.write_u8(1)?; // unk08
+        writer.write_all(&[0u8; 3])?;
+
+        writer.write_u32::<LE>(self.files.len() as u32)?;
+
+        let header_size_pos = writer.stream_position()?;
+        writer.write_u64::<LE>(0)?; // header_size, patched below
+
+        writer.write_all(b"00000000")?; // version, format stable across the revisions we write
+
+        let file_header_size = if self.extended == 4 { 0x40 } else { 0x20 };
+        writer.write_u64::<LE>(file_header_size)?;
+        // file_header_offset, fixed by the layout below: the entry table follows the header, which
+        // carries an extra hash table offset field for `extended == 4` containers.
+        writer.write_u64::<LE>(if self.extended == 4 { 0x40 } else { 0x38 })?;
+
+        writer.write_u8(self.unicode as u8)?;
+        writer.write_u8(self.raw_format)?;
+        writer.write_u8(self.extended)?;
+        writer.write_all(&[0u8; 5])?;
+
+        let hash_table_offset_pos = if self.extended == 4 {
+            let pos = writer.stream_position()?;
+            writer.write_u64::<LE>(0)?; // hash table offset, patched below
+            Some(pos)
+        } else {
+            None
+        };
+
+        // Reserve space for the per-entry table; string and data blocks follow it.
+        let table_start = writer.stream_position()?;
+        let table_len = self.files.len() as u64 * file_header_size;
+        writer.seek(SeekFrom::Start(table_start + table_len))?;
+
+        let mut name_offsets = Vec::with_capacity(self.files.len());
+        for entry in &self.files {
+            name_offsets.push(writer.stream_position()?);
+            if self.unicode {
+                writer.write_utf16_nul_terminated(&entry.path)?;
+            } else {
+                writer.write_shift_jis_nul_terminated(&entry.path)?;
+            }
+        }
+
+        let mut data_offsets = Vec::with_capacity(self.files.len());
+        for content in contents {
+            pad_to_alignment(writer, DATA_ALIGNMENT)?;
+            data_offsets.push(writer.stream_position()?);
+            writer.write_all(content)?;
+        }
+
+        let hash_table_offset = if let Some(offset_pos) = hash_table_offset_pos {
+            let offset = writer.stream_position()?;
+            write_hash_table(writer, &self.files)?;
+            Some((offset_pos, offset))
+        } else {
+            None
+        };
+
+        let end_pos = writer.stream_position()?;
+
+        writer.seek(SeekFrom::Start(table_start))?;
+        for (i, entry) in self.files.iter().enumerate() {
+            let entry_start = writer.stream_position()?;
+
+            writer.write_u8(entry.flags)?;
+            writer.write_all(&[0u8; 3])?;
+            writer.write_i32::<LE>(-1)?; // breakable_size: unused by containers we rebuild
+            writer.write_u64::<LE>(data_offsets[i])?;
+            writer.write_i32::<LE>(entry.id)?;
+            writer.write_i32::<LE>(name_offsets[i] as i32)?;
+            writer.write_u64::<LE>(contents[i].len() as u64)?;
+
+            // Pad out to the declared stride so any reader that honors `file_header_size`
+            // (rather than assuming it matches the fields we actually know about) stays aligned.
+            writer.seek(SeekFrom::Start(entry_start + file_header_size))?;
+        }
+
+        if let Some((offset_pos, offset)) = hash_table_offset {
+            writer.seek(SeekFrom::Start(offset_pos))?;
+            writer.write_u64::<LE>(offset)?;
+        }
+
+        writer.seek(SeekFrom::Start(header_size_pos))?;
+        writer.write_u64::<LE>(table_start)?;
+
+        writer.seek(SeekFrom::Start(end_pos))?;
+        Ok(())
+    }
+}
+
+fn pad_to_alignment<W: Write + Seek>(writer: &mut W, alignment: u64) -> io::Result<()> {
+    let pos = writer.stream_position()?;
+    let padded = (pos + alignment - 1) / alignment * alignment;
+    if padded > pos {
+        writer.write_all(&vec![0u8; (padded - pos) as usize])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn sample_bnd4(extended: u8) -> (BND4, Vec<Vec<u8>>) {
+        let mut bnd4 = BND4::from_entries(vec![
+            (0, "N:\\GR\\data\\INTERROOT_win64\\map\\m10\\m10_00_00_00\\a.tae".to_string()),
+            (1, "N:\\GR\\data\\INTERROOT_win64\\map\\m10\\m10_00_00_00\\b.tae".to_string()),
+            (2, "N:\\GR\\data\\INTERROOT_win64\\map\\m10\\m10_00_00_00\\c.tae".to_string()),
+        ]);
+        bnd4.extended = extended;
+        let contents = vec![b"aaa".to_vec(), b"bb".to_vec(), b"c".to_vec()];
+        (bnd4, contents)
+    }
+
+    #[test]
+    fn entries_round_trip_through_to_writer_and_from_reader() {
+        let (bnd4, contents) = sample_bnd4(0);
+
+        let mut buf = Cursor::new(Vec::new());
+        bnd4.to_writer(&mut buf, &contents).unwrap();
+
+        let data = buf.into_inner();
+        let parsed = BND4::from_reader(&mut Cursor::new(&data)).unwrap();
+
+        assert_eq!(parsed.files.len(), bnd4.files.len());
+        for (entry, expected) in parsed.files.iter().zip(&contents) {
+            assert_eq!(entry.bytes(&data).unwrap(), expected.as_slice());
+        }
+    }
+
+    #[test]
+    fn hash_table_round_trips_through_to_writer_and_from_reader() {
+        let (bnd4, contents) = sample_bnd4(4);
+
+        let mut buf = Cursor::new(Vec::new());
+        bnd4.to_writer(&mut buf, &contents).unwrap();
+
+        let parsed = BND4::from_reader(&mut Cursor::new(buf.into_inner())).unwrap();
+        let hash_table = parsed.hash_table.as_ref().expect("extended == 4 archive should have a hash table");
+
+        assert_eq!(hash_table.len(), parsed.files.len());
+        for entry in hash_table {
+            let file = &parsed.files[entry.file_index as usize];
+            assert_eq!(entry.hash, path_hash(&file.path));
+        }
+    }
+
+    #[test]
+    fn check_flags_a_hash_table_entry_with_a_stale_hash() {
+        let (bnd4, contents) = sample_bnd4(4);
+
+        let mut buf = Cursor::new(Vec::new());
+        bnd4.to_writer(&mut buf, &contents).unwrap();
+        let mut parsed = BND4::from_reader(&mut Cursor::new(buf.into_inner())).unwrap();
+
+        parsed.hash_table.as_mut().unwrap()[0].hash ^= 1;
+        let anomalies = parsed.check(usize::MAX);
+        assert_eq!(anomalies.len(), 1);
+        assert!(anomalies[0].message.contains("expected"));
+    }
+
+    #[test]
+    fn repair_rebuilds_the_hash_table_for_the_surviving_files() {
+        let (mut bnd4, _) = sample_bnd4(4);
+        bnd4.files[1].path = String::new(); // check() would flag this entry, so repair() drops it
+
+        let (repaired, contents) = bnd4.repair(&[0u8; 16]);
+
+        assert_eq!(repaired.files.len(), 2);
+        assert_eq!(contents.len(), 2);
+        let hash_table = repaired.hash_table.as_ref().unwrap();
+        assert_eq!(hash_table.len(), repaired.files.len());
+        for entry in hash_table {
+            let file = &repaired.files[entry.file_index as usize];
+            assert_eq!(entry.hash, path_hash(&file.path));
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! DCX: the compressed container FromSoftware wraps almost every other format in.
+//!
+//! A DCX file is a small chain of fixed-size chunks (`DCX\0`, `DCS\0`, `DCP\0`, `DCA\0`) describing
+//! a single compressed blob, followed by the blob itself. [`Dcx::parse`] reads the chunk chain and
+//! hands back a view onto the still-compressed data; [`Dcx::create_decoder`] wraps that view in a
+//! [`Read`] implementation for the blob's compression method. [`Dcx::encode`] is the inverse: given
+//! an already-decompressed buffer, it compresses it and re-assembles the chunk chain.
+
+use std::io::{self, Cursor, Read, Write};
+
+use byteorder::{WriteBytesExt, BE};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::io_ext::{Endian, ReadFormatsExt};
+use crate::oodle;
+
+/// Compression method recorded in a DCX file's `DCP\0` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DcxMethod {
+    /// Plain zlib (`DFLT`), used by most non-Elden-Ring titles.
+    Deflate,
+    /// Oodle Kraken (`KRAK`), used by Elden Ring and later.
+    Kraken,
+}
+
+impl DcxMethod {
+    fn from_tag(tag: &[u8; 4]) -> io::Result<Self> {
+        match tag {
+            b"DFLT" => Ok(DcxMethod::Deflate),
+            b"KRAK" => Ok(DcxMethod::Kraken),
+            _ => Err(io::Error::other(format!(
+                "unsupported DCX compression tag {:?}",
+                String::from_utf8_lossy(tag)
+            ))),
+        }
+    }
+
+    fn tag(self) -> [u8; 4] {
+        match self {
+            DcxMethod::Deflate => *b"DFLT",
+            DcxMethod::Kraken => *b"KRAK",
+        }
+    }
+
+    /// Stable, human-readable name for this method, used to persist it outside a DCX file (e.g. in
+    /// `dcx-extract`'s manifest) without leaning on the `Debug` format.
+    pub fn name(self) -> &'static str {
+        match self {
+            DcxMethod::Deflate => "deflate",
+            DcxMethod::Kraken => "kraken",
+        }
+    }
+
+    /// Parse the name produced by [`DcxMethod::name`].
+    pub fn from_name(name: &str) -> io::Result<Self> {
+        match name {
+            "deflate" => Ok(DcxMethod::Deflate),
+            "kraken" => Ok(DcxMethod::Kraken),
+            _ => Err(io::Error::other(format!("unknown DCX method {name:?}"))),
+        }
+    }
+}
+
+/// A single validation finding from [`Dcx::check`], anchored to the byte offset it was found at.
+#[derive(Debug, Clone)]
+pub struct Anomaly {
+    pub offset: u64,
+    pub message: String,
+}
+
+/// A parsed DCX header plus a borrowed view of the (still compressed) payload.
+pub struct Dcx<'a> {
+    method: DcxMethod,
+    dcs_offset: u64,
+    uncompressed_size: usize,
+    compressed_size: usize,
+    compressed: &'a [u8],
+}
+
+impl<'a> Dcx<'a> {
+    /// Parse the DCX chunk chain at the start of `data`.
+    pub fn parse(data: &'a [u8]) -> io::Result<Self> {
+        let mut cursor = Cursor::new(data);
+
+        cursor.read_magic(b"DCX\0")?;
+        let _unk04 = cursor.read_u32(Endian::Big)?;
+        let dcs_offset = cursor.read_u32(Endian::Big)? as u64;
+        let dcp_offset = cursor.read_u32(Endian::Big)? as u64;
+        let dca_offset = cursor.read_u32(Endian::Big)? as u64;
+        let _unk14 = cursor.read_u32(Endian::Big)?;
+
+        cursor.set_position(dcs_offset);
+        cursor.read_magic(b"DCS\0")?;
+        let uncompressed_size = cursor.read_u32(Endian::Big)? as usize;
+        let compressed_size = cursor.read_u32(Endian::Big)? as usize;
+
+        cursor.set_position(dcp_offset);
+        cursor.read_magic(b"DCP\0")?;
+        let mut method_tag = [0u8; 4];
+        cursor.read_exact(&mut method_tag)?;
+        let method = DcxMethod::from_tag(&method_tag)?;
+
+        // The rest of the DCP chunk (compression level + reserved fields) isn't needed to decode
+        // the payload; the header's own `dca_offset` field already tells us exactly where the DCA
+        // chunk starts, so jump there directly instead of trying to size the DCP chunk ourselves.
+        cursor.set_position(dca_offset);
+        cursor.read_magic(b"DCA\0")?;
+        let _dca_size = cursor.read_u32(Endian::Big)?;
+        let data_offset = cursor.position();
+
+        let compressed = data
+            .get(data_offset as usize..data_offset as usize + compressed_size)
+            .ok_or_else(|| io::Error::other("DCX compressed payload out of bounds"))?;
+
+        Ok(Dcx { method, dcs_offset, uncompressed_size, compressed_size, compressed })
+    }
+
+    /// The compression method this archive was encoded with, so callers round-tripping it (e.g.
+    /// `repack`/`repair`) can re-encode with the same method instead of guessing.
+    pub fn method(&self) -> DcxMethod {
+        self.method
+    }
+
+    /// The declared size of the decompressed payload; a sizing hint, not a verified value.
+    pub fn hint_size(&self) -> usize {
+        self.uncompressed_size
+    }
+
+    /// Verify the declared block sizes against the actual decoded payload, without panicking on a
+    /// corrupt or truncated archive.
+    pub fn check(&self) -> Vec<Anomaly> {
+        let mut anomalies = Vec::new();
+
+        if self.compressed.len() != self.compressed_size {
+            anomalies.push(Anomaly {
+                offset: self.dcs_offset,
+                message: format!(
+                    "DCS declares compressed size {}, but only {} bytes are available",
+                    self.compressed_size,
+                    self.compressed.len()
+                ),
+            });
+        }
+
+        match self.create_decoder().and_then(|mut decoder| {
+            let mut actual = Vec::new();
+            decoder.read_to_end(&mut actual).map(|_| actual)
+        }) {
+            Ok(actual) if actual.len() != self.uncompressed_size => {
+                anomalies.push(Anomaly {
+                    offset: self.dcs_offset,
+                    message: format!(
+                        "DCS declares uncompressed size {}, but decoding produced {} bytes",
+                        self.uncompressed_size,
+                        actual.len()
+                    ),
+                });
+            }
+            Ok(_) => {}
+            Err(e) => anomalies.push(Anomaly {
+                offset: self.dcs_offset,
+                message: format!("failed to decode payload: {e}"),
+            }),
+        }
+
+        anomalies
+    }
+
+    /// Create a streaming decoder over the compressed payload.
+    pub fn create_decoder(&self) -> io::Result<Box<dyn Read + 'a>> {
+        match self.method {
+            DcxMethod::Deflate => Ok(Box::new(ZlibDecoder::new(self.compressed))),
+            DcxMethod::Kraken => {
+                let decompressed = oodle::decompress(self.compressed, self.uncompressed_size)?;
+                Ok(Box::new(Cursor::new(decompressed)))
+            }
+        }
+    }
+
+    /// Compress `data` and wrap it back up in a full DCX chunk chain.
+    ///
+    /// This mirrors the header layout [`Dcx::parse`] reads: a fixed `DCX\0`/`DCS\0`/`DCP\0`/`DCA\0`
+    /// chain immediately followed by the compressed blob.
+    pub fn encode(data: &[u8], method: DcxMethod) -> io::Result<Vec<u8>> {
+        let compressed = match method {
+            DcxMethod::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+            DcxMethod::Kraken => oodle::compress(data)?,
+        };
+
+        // DCP chunk layout: magic(4) + method tag(4) + u32(4) + level u8(1) + reserved(3) +
+        // 4 reserved u32s(16) = 0x20 bytes, immediately following the DCS chunk.
+        let dcs_offset = 0x18u32;
+        let dcp_offset = dcs_offset + 0xC; // "DCS\0" + uncompressed_size + compressed_size
+        let dca_offset = dcp_offset + 0x20;
+
+        let mut out = Vec::with_capacity(compressed.len() + 0x40);
+
+        out.write_all(b"DCX\0")?;
+        out.write_u32::<BE>(0x10000)?;
+        out.write_u32::<BE>(dcs_offset)?;
+        out.write_u32::<BE>(dcp_offset)?;
+        out.write_u32::<BE>(dca_offset)?;
+        out.write_u32::<BE>(dcp_offset)?; // unk14, matches dcp_offset for the layouts this crate writes
+
+        out.write_all(b"DCS\0")?;
+        out.write_u32::<BE>(data.len() as u32)?;
+        out.write_u32::<BE>(compressed.len() as u32)?;
+
+        out.write_all(b"DCP\0")?;
+        out.write_all(&method.tag())?;
+        out.write_u32::<BE>(0x20)?;
+        out.write_u8(if method == DcxMethod::Kraken { 9 } else { 0 })?;
+        out.write_all(&[0u8; 3])?;
+        out.write_u32::<BE>(0)?;
+        out.write_u32::<BE>(0)?;
+        out.write_u32::<BE>(0)?;
+        out.write_u32::<BE>(0)?;
+
+        out.write_all(b"DCA\0")?;
+        out.write_u32::<BE>(8)?;
+
+        debug_assert_eq!(out.len() as u32, dca_offset + 8, "dca_offset math drifted from the bytes actually written");
+
+        out.extend_from_slice(&compressed);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deflate_round_trips_through_encode_and_parse() {
+        let original = b"some FromSoftware file bytes, repeated for a bit of compressibility "
+            .repeat(8);
+
+        let encoded = Dcx::encode(&original, DcxMethod::Deflate).unwrap();
+        let dcx = Dcx::parse(&encoded).unwrap();
+
+        let mut decoded = Vec::new();
+        dcx.create_decoder().unwrap().read_to_end(&mut decoded).unwrap();
+
+        assert_eq!(decoded, original);
+        assert!(dcx.check().is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+//! Decoding of individual vertex buffer layout members into `f32` components.
+//!
+//! A [`FlverBufferLayout`](super::mesh::FlverBufferLayout) describes, member by member, how a
+//! mesh's raw vertex bytes map onto semantic attributes (position, normal, UVs, ...). This module
+//! turns a single member's raw storage representation into plain `[f32; N]` data, applying
+//! whatever fixed-point scale the storage type implies.
+
+use byteorder::ByteOrder;
+
+/// Semantic meaning of a single [`FlverBufferLayoutMember`](super::mesh::FlverBufferLayoutMember).
+///
+/// Mirrors the semantic IDs used across FLVER0/FLVER2 revisions; unrecognized values are kept
+/// around verbatim so callers can still skip over them without failing the whole parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberSemantic {
+    Position,
+    BoneWeights,
+    BoneIndices,
+    Normal,
+    UV,
+    Tangent,
+    Bitangent,
+    VertexColor,
+    Unknown(u32),
+}
+
+impl MemberSemantic {
+    pub(crate) fn from_raw(value: u32) -> Self {
+        match value {
+            0 => MemberSemantic::Position,
+            1 => MemberSemantic::BoneWeights,
+            2 => MemberSemantic::BoneIndices,
+            3 => MemberSemantic::Normal,
+            5 => MemberSemantic::UV,
+            6 => MemberSemantic::Tangent,
+            7 => MemberSemantic::Bitangent,
+            10 => MemberSemantic::VertexColor,
+            other => MemberSemantic::Unknown(other),
+        }
+    }
+}
+
+/// On-disk storage representation of a single [`FlverBufferLayoutMember`](super::mesh::FlverBufferLayoutMember).
+///
+/// Each variant knows its own byte size (via [`MemberStorage::byte_size`]) and how to expand
+/// itself into floating point components (via [`MemberStorage::decode`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberStorage {
+    Float2,
+    Float3,
+    Float4,
+    Byte4A,
+    Byte4B,
+    Byte4C,
+    UByte4Norm,
+    Short2ToFloat2,
+    Short4ToFloat4A,
+    UVPair,
+    Unknown(u16),
+}
+
+impl MemberStorage {
+    pub(crate) fn from_raw(value: u32) -> Self {
+        match value {
+            0x01 => MemberStorage::Float2,
+            0x02 => MemberStorage::Float3,
+            0x03 => MemberStorage::Float4,
+            0x11 => MemberStorage::Byte4A,
+            0x13 => MemberStorage::Byte4B,
+            0x15 => MemberStorage::Byte4C,
+            0x17 => MemberStorage::UByte4Norm,
+            0x19 => MemberStorage::Short2ToFloat2,
+            0x1a => MemberStorage::UVPair,
+            0x2f => MemberStorage::Short4ToFloat4A,
+            other => MemberStorage::Unknown(other as u16),
+        }
+    }
+
+    /// Size, in bytes, of this member's storage within the vertex stride.
+    pub fn byte_size(self) -> usize {
+        match self {
+            MemberStorage::Float2 => 8,
+            MemberStorage::Float3 => 12,
+            MemberStorage::Float4 => 16,
+            MemberStorage::Byte4A
+            | MemberStorage::Byte4B
+            | MemberStorage::Byte4C
+            | MemberStorage::UByte4Norm
+            | MemberStorage::Short2ToFloat2 => 4,
+            MemberStorage::UVPair | MemberStorage::Short4ToFloat4A => 8,
+            MemberStorage::Unknown(_) => 0,
+        }
+    }
+
+    /// Decode the raw bytes for a single member into up to 4 floating point components.
+    ///
+    /// `bytes` must be at least [`MemberStorage::byte_size`] long. `uv_scale` is applied to the
+    /// short-backed UV variants, which store texture coordinates as fixed-point shorts rather
+    /// than floats. `O` is the byte order of the backing FLVER, matching the one used for the
+    /// header and table fields.
+    pub fn decode<O: ByteOrder>(self, bytes: &[u8], uv_scale: f32) -> [f32; 4] {
+        match self {
+            MemberStorage::Float2 => {
+                [O::read_f32(&bytes[0..4]), O::read_f32(&bytes[4..8]), 0.0, 0.0]
+            }
+            MemberStorage::Float3 => [
+                O::read_f32(&bytes[0..4]),
+                O::read_f32(&bytes[4..8]),
+                O::read_f32(&bytes[8..12]),
+                0.0,
+            ],
+            MemberStorage::Float4 => [
+                O::read_f32(&bytes[0..4]),
+                O::read_f32(&bytes[4..8]),
+                O::read_f32(&bytes[8..12]),
+                O::read_f32(&bytes[12..16]),
+            ],
+            // Signed, normalized by 127: used for tangents/bitangents/normals.
+            MemberStorage::Byte4A | MemberStorage::Byte4C => [
+                (bytes[0] as i8) as f32 / 127.0,
+                (bytes[1] as i8) as f32 / 127.0,
+                (bytes[2] as i8) as f32 / 127.0,
+                (bytes[3] as i8) as f32 / 127.0,
+            ],
+            // Raw unsigned bytes: used for bone indices, which must survive as integers to look
+            // up into the bone table rather than being normalized into `0.0..1.0`. Each index
+            // fits exactly in an `f32`, so no precision is lost by leaving it unscaled.
+            MemberStorage::Byte4B => {
+                [bytes[0] as f32, bytes[1] as f32, bytes[2] as f32, bytes[3] as f32]
+            }
+            // Unsigned, normalized by 255: used for vertex colors.
+            MemberStorage::UByte4Norm => [
+                bytes[0] as f32 / 255.0,
+                bytes[1] as f32 / 255.0,
+                bytes[2] as f32 / 255.0,
+                bytes[3] as f32 / 255.0,
+            ],
+            MemberStorage::Short2ToFloat2 => {
+                let u = O::read_i16(&bytes[0..2]);
+                let v = O::read_i16(&bytes[2..4]);
+                [u as f32 / uv_scale, v as f32 / uv_scale, 0.0, 0.0]
+            }
+            MemberStorage::UVPair => {
+                let u0 = O::read_i16(&bytes[0..2]);
+                let v0 = O::read_i16(&bytes[2..4]);
+                let u1 = O::read_i16(&bytes[4..6]);
+                let v1 = O::read_i16(&bytes[6..8]);
+                [u0 as f32 / uv_scale, v0 as f32 / uv_scale, u1 as f32 / uv_scale, v1 as f32 / uv_scale]
+            }
+            MemberStorage::Short4ToFloat4A => {
+                let x = O::read_i16(&bytes[0..2]);
+                let y = O::read_i16(&bytes[2..4]);
+                let z = O::read_i16(&bytes[4..6]);
+                let w = O::read_i16(&bytes[6..8]);
+                [x as f32 / 32767.0, y as f32 / 32767.0, z as f32 / 32767.0, w as f32 / 32767.0]
+            }
+            MemberStorage::Unknown(_) => [0.0, 0.0, 0.0, 0.0],
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Geometry tables (materials, bones, meshes, face sets, vertex buffers, buffer layouts) that
+//! follow the dummy array, and the owned, decoded [`Mesh`] output built from them.
+
+use byteorder::ByteOrder;
+use zerocopy::{FromBytes, FromZeroes, F32, I16, I32, U16, U32};
+
+use crate::flver::accessor::{MemberSemantic, MemberStorage};
+use crate::io_ext::zerocopy::Padding;
+
+#[derive(FromZeroes, FromBytes)]
+#[repr(packed)]
+pub(crate) struct FlverMaterialData<O: ByteOrder> {
+    pub(crate) name_offset: U32<O>,
+    pub(crate) mtd_offset: U32<O>,
+    pub(crate) texture_count: U32<O>,
+    pub(crate) texture_index: U32<O>,
+    pub(crate) flags: U32<O>,
+    pub(crate) gx_offset: U32<O>,
+    pub(crate) unk18: U32<O>,
+    _padding: Padding<12>,
+}
+
+#[derive(FromZeroes, FromBytes)]
+#[repr(packed)]
+pub(crate) struct FlverBoneData<O: ByteOrder> {
+    pub(crate) translation: [F32<O>; 3],
+    pub(crate) name_offset: U32<O>,
+    pub(crate) rotation: [F32<O>; 3],
+    pub(crate) parent_index: I16<O>,
+    pub(crate) child_index: I16<O>,
+    pub(crate) scale: [F32<O>; 3],
+    pub(crate) next_sibling_index: I16<O>,
+    pub(crate) previous_sibling_index: I16<O>,
+    pub(crate) bounding_box_min: [F32<O>; 3],
+    pub(crate) bounding_box_max: [F32<O>; 3],
+    _padding: Padding<52>,
+}
+
+#[derive(FromZeroes, FromBytes)]
+#[repr(packed)]
+pub(crate) struct FlverMeshData<O: ByteOrder> {
+    pub(crate) dynamic: u8,
+    _padding0: Padding<3>,
+    pub(crate) material_index: I32<O>,
+    _padding1: Padding<8>,
+    pub(crate) default_bone_index: I32<O>,
+    pub(crate) bone_count: I32<O>,
+    _padding2: Padding<8>,
+    pub(crate) bone_offset: U32<O>,
+    pub(crate) face_set_count: I32<O>,
+    pub(crate) face_set_index_offset: U32<O>,
+    pub(crate) vertex_buffer_count: I32<O>,
+    pub(crate) vertex_buffer_index_offset: U32<O>,
+}
+
+#[derive(FromZeroes, FromBytes)]
+#[repr(packed)]
+pub(crate) struct FlverFaceSetData<O: ByteOrder> {
+    pub(crate) flags: U32<O>,
+    pub(crate) triangle_strip: U32<O>,
+    pub(crate) cull_back_faces: U32<O>,
+    pub(crate) unk0c: U32<O>,
+    pub(crate) index_count: I32<O>,
+    pub(crate) index_offset: U32<O>,
+    _padding: Padding<16>,
+}
+
+impl<O: ByteOrder> FlverFaceSetData<O> {
+    fn is_triangle_strip(&self) -> bool {
+        self.triangle_strip.get() != 0
+    }
+}
+
+#[derive(FromZeroes, FromBytes)]
+#[repr(packed)]
+pub(crate) struct FlverVertexBufferData<O: ByteOrder> {
+    pub(crate) buffer_index: I32<O>,
+    pub(crate) layout_index: I32<O>,
+    pub(crate) vertex_size: I32<O>,
+    pub(crate) vertex_count: I32<O>,
+    _padding: Padding<8>,
+    pub(crate) buffer_length: I32<O>,
+    pub(crate) buffer_offset: U32<O>,
+}
+
+#[derive(FromZeroes, FromBytes)]
+#[repr(packed)]
+pub(crate) struct FlverBufferLayoutData<O: ByteOrder> {
+    pub(crate) member_count: I32<O>,
+    _padding: Padding<12>,
+}
+
+#[derive(FromZeroes, FromBytes)]
+#[repr(packed)]
+pub(crate) struct FlverBufferLayoutMemberData<O: ByteOrder> {
+    pub(crate) unk0: U32<O>,
+    pub(crate) struct_offset: U32<O>,
+    pub(crate) storage_type: U32<O>,
+    pub(crate) semantic: U32<O>,
+    pub(crate) index: U32<O>,
+}
+
+/// A single decoded FLVER mesh, owned and detached from the backing byte slice.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<Vec<[f32; 2]>>,
+    pub indices: Vec<u32>,
+    pub material_index: u32,
+}
+
+/// Scale applied to short-backed UV members; FLVER stores texture coordinates at a fixed 1/1024
+/// texel granularity regardless of buffer layout revision.
+const UV_SCALE: f32 = 1024.0;
+
+pub(crate) fn decode_mesh<O: ByteOrder>(
+    data: &[u8],
+    data_offset: usize,
+    mesh: &FlverMeshData<O>,
+    face_sets: &[FlverFaceSetData<O>],
+    vertex_buffers: &[FlverVertexBufferData<O>],
+    buffer_layouts: &[FlverBufferLayoutData<O>],
+    layout_members: &[FlverBufferLayoutMemberData<O>],
+    vertex_index_size: u8,
+) -> Option<Mesh> {
+    let face_set_count = mesh.face_set_count.get() as usize;
+    let face_set_index_offset = mesh.face_set_index_offset.get() as usize;
+    let (face_set_indices, _) = U32::<O>::slice_from_prefix(
+        data.get(face_set_index_offset..)?,
+        face_set_count,
+    )?;
+
+    let vertex_buffer_count = mesh.vertex_buffer_count.get() as usize;
+    let vertex_buffer_index_offset = mesh.vertex_buffer_index_offset.get() as usize;
+    let (vertex_buffer_indices, _) = U32::<O>::slice_from_prefix(
+        data.get(vertex_buffer_index_offset..)?,
+        vertex_buffer_count,
+    )?;
+
+    let mut out = Mesh {
+        material_index: mesh.material_index.get() as u32,
+        ..Default::default()
+    };
+
+    for buffer_index in vertex_buffer_indices {
+        let buffer = vertex_buffers.get(buffer_index.get() as usize)?;
+        let layout = buffer_layouts.get(buffer.layout_index.get() as usize)?;
+
+        let member_count = layout.member_count.get() as usize;
+        let first_member_index: usize = buffer_layouts[..buffer.layout_index.get() as usize]
+            .iter()
+            .map(|l| l.member_count.get() as usize)
+            .sum();
+        let members = layout_members.get(first_member_index..first_member_index + member_count)?;
+
+        let vertex_count = buffer.vertex_count.get() as usize;
+        let vertex_size = buffer.vertex_size.get() as usize;
+        let buffer_start = data_offset + buffer.buffer_offset.get() as usize;
+
+        let mut uv_slots: Vec<usize> = Vec::new();
+
+        for vertex_index in 0..vertex_count {
+            let vertex_start = buffer_start + vertex_index * vertex_size;
+            let vertex_bytes = data.get(vertex_start..vertex_start + vertex_size)?;
+
+            for member in members {
+                let storage = MemberStorage::from_raw(member.storage_type.get());
+                let semantic = MemberSemantic::from_raw(member.semantic.get());
+                let offset = member.struct_offset.get() as usize;
+                let size = storage.byte_size();
+                if size == 0 {
+                    continue;
+                }
+                let field = vertex_bytes.get(offset..offset + size)?;
+                let decoded = storage.decode::<O>(field, UV_SCALE);
+
+                match semantic {
+                    MemberSemantic::Position => {
+                        if out.positions.len() <= vertex_index {
+                            out.positions.resize(vertex_index + 1, [0.0; 3]);
+                        }
+                        out.positions[vertex_index] = [decoded[0], decoded[1], decoded[2]];
+                    }
+                    MemberSemantic::Normal => {
+                        if out.normals.len() <= vertex_index {
+                            out.normals.resize(vertex_index + 1, [0.0; 3]);
+                        }
+                        out.normals[vertex_index] = [decoded[0], decoded[1], decoded[2]];
+                    }
+                    MemberSemantic::UV => {
+                        let index = member.index.get() as usize;
+                        let slot = uv_slots.iter().position(|&s| s == index).unwrap_or_else(|| {
+                            uv_slots.push(index);
+                            out.uvs.push(Vec::new());
+                            uv_slots.len() - 1
+                        });
+                        if out.uvs[slot].len() <= vertex_index {
+                            out.uvs[slot].resize(vertex_index + 1, [0.0; 2]);
+                        }
+                        out.uvs[slot][vertex_index] = [decoded[0], decoded[1]];
+
+                        // `UVPair` packs two UV channels into one member; stash the second.
+                        if storage == MemberStorage::UVPair {
+                            let second_slot = uv_slots.iter().position(|&s| s == index + 1).unwrap_or_else(|| {
+                                uv_slots.push(index + 1);
+                                out.uvs.push(Vec::new());
+                                uv_slots.len() - 1
+                            });
+                            if out.uvs[second_slot].len() <= vertex_index {
+                                out.uvs[second_slot].resize(vertex_index + 1, [0.0; 2]);
+                            }
+                            out.uvs[second_slot][vertex_index] = [decoded[2], decoded[3]];
+                        }
+                    }
+                    // Tangent/bitangent/bone weight/bone index/vertex color are parsed but not
+                    // currently surfaced on `Mesh`; extend `Mesh` if a consumer needs them.
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    for face_set_index in face_set_indices {
+        let face_set = face_sets.get(face_set_index.get() as usize)?;
+        let index_count = face_set.index_count.get() as usize;
+        let index_offset = face_set.index_offset.get() as usize;
+
+        let indices = read_indices::<O>(data, index_offset, index_count, vertex_index_size)?;
+
+        if face_set.is_triangle_strip() {
+            out.indices.extend(unswizzle_triangle_strip(&indices));
+        } else {
+            out.indices.extend(indices);
+        }
+    }
+
+    Some(out)
+}
+
+fn read_indices<O: ByteOrder>(
+    data: &[u8],
+    offset: usize,
+    count: usize,
+    vertex_index_size: u8,
+) -> Option<Vec<u32>> {
+    match vertex_index_size {
+        16 => {
+            let (shorts, _) = U16::<O>::slice_from_prefix(data.get(offset..)?, count)?;
+            // Widen the 16-bit restart sentinel (`0xFFFF`) to the 32-bit one `unswizzle_triangle_strip`
+            // checks for, rather than zero-extending it to `0x0000_FFFF`, which would never match.
+            Some(shorts
+                .iter()
+                .map(|v| match v.get() {
+                    0xFFFF => 0xFFFF_FFFF,
+                    v => v as u32,
+                })
+                .collect())
+        }
+        _ => {
+            let (words, _) = U32::<O>::slice_from_prefix(data.get(offset..)?, count)?;
+            Some(words.iter().map(|v| v.get()).collect())
+        }
+    }
+}
+
+/// Expand a triangle-strip face set into a flat triangle-list, honoring the `0xFFFFFFFF`
+/// (or `0xFFFF`, already widened by [`read_indices`]) restart marker FLVER uses between strips.
+fn unswizzle_triangle_strip(strip: &[u32]) -> Vec<u32> {
+    const RESTART: u32 = 0xFFFF_FFFF;
+    let mut triangles = Vec::new();
+    let mut run_start = 0usize;
+
+    for i in 0..strip.len() {
+        if strip[i] == RESTART {
+            run_start = i + 1;
+            continue;
+        }
+        let offset = i - run_start;
+        if offset < 2 {
+            continue;
+        }
+        let (a, b, c) = (strip[i - 2], strip[i - 1], strip[i]);
+        if a == RESTART || b == RESTART {
+            continue;
+        }
+        if offset % 2 == 0 {
+            triangles.extend_from_slice(&[a, b, c]);
+        } else {
+            triangles.extend_from_slice(&[b, a, c]);
+        }
+    }
+
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::LittleEndian;
+
+    use super::*;
+
+    #[test]
+    fn read_indices_widens_the_16_bit_restart_sentinel() {
+        // Two triangle strips (0,1,2,3) and (4,5,6) separated by a 0xFFFF restart marker.
+        let mut data = Vec::new();
+        for v in [0u16, 1, 2, 3, 0xFFFF, 4, 5, 6] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let indices = read_indices::<LittleEndian>(&data, 0, 8, 16).unwrap();
+        assert_eq!(indices, vec![0, 1, 2, 3, 0xFFFF_FFFF, 4, 5, 6]);
+    }
+
+    #[test]
+    fn unswizzle_triangle_strip_restarts_on_the_widened_sentinel() {
+        let strip = vec![0, 1, 2, 3, 0xFFFF_FFFF, 4, 5, 6];
+        let triangles = unswizzle_triangle_strip(&strip);
+
+        // Strip 1 (0,1,2,3) -> (0,1,2),(2,1,3); strip 2 (4,5,6) -> (4,5,6). A bug that fails to
+        // recognize the restart would instead stitch a bogus triangle across the two strips.
+        assert_eq!(triangles, vec![0, 1, 2, 2, 1, 3, 4, 5, 6]);
+    }
+}
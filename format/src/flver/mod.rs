@@ -1,50 +1,120 @@
 use std::{io::Read, ops::Deref};
 use std::fmt::{Debug, Formatter};
 
-use ::zerocopy::{FromBytes, FromZeroes, Ref, F32, U32};
+use ::zerocopy::{FromBytes, FromZeroes, Ref, F32, I16, U32};
 use byteorder::{ByteOrder, BE, LE};
 
 use crate::{
-    flver::dummy::{FlverDummy, FlverDummyData},
+    flver::mesh::{
+        decode_mesh, FlverBufferLayoutData, FlverBufferLayoutMemberData, FlverFaceSetData,
+        FlverMeshData, FlverVertexBufferData, Mesh,
+    },
     io_ext::{zerocopy::Padding, ReadFormatsExt},
 };
 
 pub mod accessor;
-mod dummy;
 mod mesh;
-pub mod reader;
 
-pub enum Flver<'a> {
-    LittleEndian(FlverInner<'a, LE>),
-    BigEndian(FlverInner<'a, BE>),
+/// One entry of the dummy array: a named attach point (for particle effects, weapon sheathes,
+/// etc.) anchored to a bone, read straight out of the mapped buffer without copying.
+#[derive(FromZeroes, FromBytes)]
+#[repr(packed)]
+pub(crate) struct FlverDummyData<O: ByteOrder> {
+    pub(crate) position: [F32<O>; 3],
+    pub(crate) color: U32<O>,
+    pub(crate) forward: [F32<O>; 3],
+    pub(crate) reference_id: I16<O>,
+    pub(crate) parent_bone_index: I16<O>,
+    pub(crate) upward: [F32<O>; 3],
+    pub(crate) attach_bone_index: I16<O>,
+    pub(crate) flag1: u8,
+    pub(crate) use_upward_vector: u8,
+    _padding: Padding<8>,
+}
+
+pub trait FlverDummy {
+    fn position(&self) -> [f32; 3];
+    fn reference_id(&self) -> i16;
+    fn parent_bone_index(&self) -> i16;
+    fn attach_bone_index(&self) -> i16;
+}
+
+impl<O: ByteOrder> FlverDummy for FlverDummyData<O> {
+    fn position(&self) -> [f32; 3] {
+        self.position.map(|v| v.get())
+    }
+
+    fn reference_id(&self) -> i16 {
+        self.reference_id.get()
+    }
+
+    fn parent_bone_index(&self) -> i16 {
+        self.parent_bone_index.get()
+    }
+
+    fn attach_bone_index(&self) -> i16 {
+        self.attach_bone_index.get()
+    }
+}
+
+/// Byte-order-erased view over a parsed [`FlverInner`].
+///
+/// `Flver` itself no longer branches on endianness at every call site: the LE/BE split is resolved
+/// once, in [`Flver::from`], by boxing the matching `FlverInner<O>` behind this trait.
+trait FlverAccess<'a> {
+    fn header(&self) -> &dyn FlverHeader;
+    fn dummy(&'a self, index: usize) -> &'a dyn FlverDummy;
+    fn meshes(&'a self) -> Box<dyn Iterator<Item = Mesh> + 'a>;
+    fn fmt_debug(&self, f: &mut Formatter<'_>) -> std::fmt::Result;
+}
+
+impl<'a, O: ByteOrder + 'static> FlverAccess<'a> for FlverInner<'a, O> {
+    fn header(&self) -> &dyn FlverHeader {
+        self.header
+    }
+
+    fn dummy(&'a self, index: usize) -> &'a dyn FlverDummy {
+        &self.dummys[index]
+    }
+
+    fn meshes(&'a self) -> Box<dyn Iterator<Item = Mesh> + 'a> {
+        Box::new(FlverInner::meshes(self))
+    }
+
+    fn fmt_debug(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+pub struct Flver<'a> {
+    inner: Box<dyn FlverAccess<'a> + 'a>,
 }
 
 impl<'a> Deref for Flver<'a> {
     type Target = dyn FlverHeader;
 
     fn deref(&self) -> &Self::Target {
-        match self {
-            Flver::LittleEndian(inner) => inner.header,
-            Flver::BigEndian(inner) => inner.header,
-        }
+        self.inner.header()
     }
 }
 
 impl<'a> Flver<'a> {
     pub fn dummy(&'a self, index: usize) -> &'a dyn FlverDummy {
-        match self {
-            Flver::LittleEndian(inner) => inner.dummy(index),
-            Flver::BigEndian(inner) => inner.dummy(index),
-        }
+        self.inner.dummy(index)
+    }
+
+    /// Decode every mesh's geometry, in declaration order. A mesh whose tables or buffers fall
+    /// outside the data is skipped rather than yielded.
+    ///
+    /// Each [`Mesh`] is fully owned: it can outlive the buffer backing this `Flver`.
+    pub fn meshes(&'a self) -> Box<dyn Iterator<Item = Mesh> + 'a> {
+        self.inner.meshes()
     }
 }
 
 impl<'a> Debug for Flver<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Flver::LittleEndian(inner) => inner.fmt(f),
-            Flver::BigEndian(inner) => inner.fmt(f)
-        }
+        self.inner.fmt_debug(f)
     }
 }
 
@@ -53,9 +123,46 @@ impl<'a> Flver<'a> {
         let (header_ref, dummy_bytes) = Ref::<&'a [u8], FlverHeaderData<O>>::new_from_prefix(data)?;
         let header: &'a FlverHeaderData<O> = header_ref.into_ref();
         let dummy_count = header.dummy_count.get() as usize;
-        let (dummys, _next) = FlverDummyData::<O>::slice_from_prefix(dummy_bytes, dummy_count)?;
+        let (dummys, rest) = FlverDummyData::<O>::slice_from_prefix(dummy_bytes, dummy_count)?;
 
-        Some(FlverInner { header, dummys })
+        // Materials and bones only gate the cursor forward to the mesh table; they aren't
+        // surfaced on `Mesh` yet, so we don't keep typed handles to them.
+        let material_count = header.material_count.get() as usize;
+        let (_materials, rest) =
+            crate::flver::mesh::FlverMaterialData::<O>::slice_from_prefix(rest, material_count)?;
+        let bone_count = header.bone_count.get() as usize;
+        let (_bones, rest) =
+            crate::flver::mesh::FlverBoneData::<O>::slice_from_prefix(rest, bone_count)?;
+
+        let mesh_count = header.mesh_count.get() as usize;
+        let (meshes, rest) = FlverMeshData::<O>::slice_from_prefix(rest, mesh_count)?;
+
+        let face_set_count = header.face_set_count.get() as usize;
+        let (face_sets, rest) = FlverFaceSetData::<O>::slice_from_prefix(rest, face_set_count)?;
+
+        let vertex_buffer_count = header.vertex_buffer_count.get() as usize;
+        let (vertex_buffers, rest) =
+            FlverVertexBufferData::<O>::slice_from_prefix(rest, vertex_buffer_count)?;
+
+        let buffer_layout_count = header.buffer_layout_count.get() as usize;
+        let (buffer_layouts, rest) =
+            FlverBufferLayoutData::<O>::slice_from_prefix(rest, buffer_layout_count)?;
+
+        let layout_member_count: usize =
+            buffer_layouts.iter().map(|l| l.member_count.get() as usize).sum();
+        let (layout_members, _rest) =
+            FlverBufferLayoutMemberData::<O>::slice_from_prefix(rest, layout_member_count)?;
+
+        Some(FlverInner {
+            header,
+            dummys,
+            data,
+            meshes,
+            face_sets,
+            vertex_buffers,
+            buffer_layouts,
+            layout_members,
+        })
     }
 
     pub fn from(data: &'a [u8]) -> Result<Self, std::io::Error> {
@@ -66,19 +173,48 @@ impl<'a> Flver<'a> {
         header.read_exact(&mut endianness)?;
 
         let is_little_endian = endianness == [0x4c, 0x00];
-        let flver = if is_little_endian {
-            Self::parse(data).map(Flver::LittleEndian)
+        let inner: Option<Box<dyn FlverAccess<'a> + 'a>> = if is_little_endian {
+            Self::parse::<LE>(data).map(|inner| Box::new(inner) as Box<dyn FlverAccess<'a> + 'a>)
         } else {
-            Self::parse(data).map(Flver::BigEndian)
+            Self::parse::<BE>(data).map(|inner| Box::new(inner) as Box<dyn FlverAccess<'a> + 'a>)
         };
 
-        flver.ok_or(std::io::Error::other("data buffer was not unaligned"))
+        inner
+            .map(|inner| Flver { inner })
+            .ok_or(std::io::Error::other("data buffer was not unaligned"))
     }
 }
 
 pub struct FlverInner<'a, O: ByteOrder> {
     header: &'a FlverHeaderData<O>,
     dummys: &'a [FlverDummyData<O>],
+    data: &'a [u8],
+    meshes: &'a [FlverMeshData<O>],
+    face_sets: &'a [FlverFaceSetData<O>],
+    vertex_buffers: &'a [FlverVertexBufferData<O>],
+    buffer_layouts: &'a [FlverBufferLayoutData<O>],
+    layout_members: &'a [FlverBufferLayoutMemberData<O>],
+}
+
+impl<'a, O: ByteOrder> FlverInner<'a, O> {
+    /// Decode every mesh's geometry, in declaration order. A mesh whose tables or buffers fall
+    /// outside the data is skipped rather than yielded.
+    pub fn meshes(&'a self) -> impl Iterator<Item = Mesh> + 'a {
+        let data_offset = self.header.data_offset.get() as usize;
+        let vertex_index_size = self.header.vertex_index_size;
+        self.meshes.iter().filter_map(move |mesh| {
+            decode_mesh(
+                self.data,
+                data_offset,
+                mesh,
+                self.face_sets,
+                self.vertex_buffers,
+                self.buffer_layouts,
+                self.layout_members,
+                vertex_index_size,
+            )
+        })
+    }
 }
 
 impl<'a, O: ByteOrder> Debug for FlverInner<'a, O> {
@@ -0,0 +1,138 @@
+//! Reader/writer extensions shared by every format parser in this crate.
+//!
+//! FromSoftware's binary formats come in both little- and big-endian revisions (PC vs. the older
+//! console ports), and [`ReadFormatsExt`] lets a single parse path handle both by taking the byte
+//! order as a runtime [`Endian`] value instead of requiring a `ByteOrder` type parameter threaded
+//! through every call site. The `try_*` accessors mirror the strict ones but return `None` on a
+//! short or out-of-bounds read instead of an `io::Error`, which keeps tolerant parsing of
+//! truncated or variant-version structures from drowning in `match`/`?` noise.
+
+pub mod zerocopy;
+
+use std::io::{self, Read, Write};
+
+/// Byte order of the format being parsed, chosen at runtime from a magic/flag byte rather than
+/// baked into the type as a `byteorder::ByteOrder` parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+macro_rules! read_accessor {
+    ($strict:ident, $try_:ident, $ty:ty, $len:expr) => {
+        fn $strict(&mut self, endian: Endian) -> io::Result<$ty> {
+            let mut buf = [0u8; $len];
+            self.read_exact(&mut buf)?;
+            Ok(match endian {
+                Endian::Little => <$ty>::from_le_bytes(buf),
+                Endian::Big => <$ty>::from_be_bytes(buf),
+            })
+        }
+
+        fn $try_(&mut self, endian: Endian) -> Option<$ty> {
+            let mut buf = [0u8; $len];
+            self.read_exact(&mut buf).ok()?;
+            Some(match endian {
+                Endian::Little => <$ty>::from_le_bytes(buf),
+                Endian::Big => <$ty>::from_be_bytes(buf),
+            })
+        }
+    };
+}
+
+pub trait ReadFormatsExt: Read {
+    /// Read and verify a fixed magic/signature, failing with the expected and actual bytes on a
+    /// mismatch.
+    fn read_magic(&mut self, magic: &[u8]) -> io::Result<()> {
+        let mut buf = vec![0u8; magic.len()];
+        self.read_exact(&mut buf)?;
+        if buf != magic {
+            return Err(io::Error::other(format!(
+                "expected magic {:?}, found {:?}",
+                String::from_utf8_lossy(magic),
+                String::from_utf8_lossy(&buf)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read a single byte. Endian-independent, but kept alongside the other accessors so parsers
+    /// never need `byteorder::ReadBytesExt` in scope (its same-named, generic `read_u16`/`read_u32`/
+    /// etc. would otherwise make every multi-byte read here ambiguous).
+    fn read_u8(&mut self) -> io::Result<u8> {
+        let mut buf = [0u8; 1];
+        self.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn try_u8(&mut self) -> Option<u8> {
+        let mut buf = [0u8; 1];
+        self.read_exact(&mut buf).ok()?;
+        Some(buf[0])
+    }
+
+    read_accessor!(read_u16, try_u16, u16, 2);
+    read_accessor!(read_i16, try_i16, i16, 2);
+    read_accessor!(read_u32, try_u32, u32, 4);
+    read_accessor!(read_i32, try_i32, i32, 4);
+    read_accessor!(read_u64, try_u64, u64, 8);
+    read_accessor!(read_i64, try_i64, i64, 8);
+    read_accessor!(read_f32, try_f32, f32, 4);
+    read_accessor!(read_f64, try_f64, f64, 8);
+
+    /// Read a UTF-16 string up to (and consuming) its null terminator.
+    fn read_utf16_nul_terminated(&mut self) -> io::Result<String> {
+        let mut units = Vec::new();
+        loop {
+            let mut buf = [0u8; 2];
+            self.read_exact(&mut buf)?;
+            let unit = u16::from_le_bytes(buf);
+            if unit == 0 {
+                break;
+            }
+            units.push(unit);
+        }
+        String::from_utf16(&units).map_err(io::Error::other)
+    }
+
+    /// Read a Shift-JIS string up to (and consuming) its null terminator.
+    fn read_shift_jis_nul_terminated(&mut self) -> io::Result<String> {
+        let mut bytes = Vec::new();
+        loop {
+            let mut buf = [0u8; 1];
+            self.read_exact(&mut buf)?;
+            if buf[0] == 0 {
+                break;
+            }
+            bytes.push(buf[0]);
+        }
+        let (decoded, _, had_errors) = encoding_rs::SHIFT_JIS.decode(&bytes);
+        if had_errors {
+            return Err(io::Error::other("invalid Shift-JIS string"));
+        }
+        Ok(decoded.into_owned())
+    }
+}
+
+impl<R: Read + ?Sized> ReadFormatsExt for R {}
+
+pub trait WriteFormatsExt: Write {
+    fn write_utf16_nul_terminated(&mut self, s: &str) -> io::Result<()> {
+        for unit in s.encode_utf16() {
+            self.write_all(&unit.to_le_bytes())?;
+        }
+        self.write_all(&[0u8; 2])
+    }
+
+    fn write_shift_jis_nul_terminated(&mut self, s: &str) -> io::Result<()> {
+        let (encoded, _, had_errors) = encoding_rs::SHIFT_JIS.encode(s);
+        if had_errors {
+            return Err(io::Error::other("string is not representable in Shift-JIS"));
+        }
+        self.write_all(&encoded)?;
+        self.write_all(&[0u8])
+    }
+}
+
+impl<W: Write + ?Sized> WriteFormatsExt for W {}
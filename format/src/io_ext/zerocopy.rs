@@ -0,0 +1,9 @@
+//! Small `zerocopy` helper types shared by the slice-based parsers (currently just FLVER).
+
+use zerocopy::{FromBytes, FromZeroes};
+
+/// `N` bytes of reserved/unknown header space, kept around so `#[repr(packed)]` table structs can
+/// be derived `FromZeroes`/`FromBytes` without naming every unknown field individually.
+#[derive(FromZeroes, FromBytes)]
+#[repr(transparent)]
+pub struct Padding<const N: usize>([u8; N]);
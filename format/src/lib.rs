@@ -0,0 +1,6 @@
+pub mod bnd4;
+pub mod dcx;
+pub mod flver;
+pub mod io_ext;
+pub mod oodle;
+pub mod tpf;
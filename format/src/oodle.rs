@@ -0,0 +1,151 @@
+//! Thin bindings to Oodle's Kraken codec, used by DCX's `KRAK` compression method.
+//!
+//! FromSoftware ships the proprietary `oo2core` library alongside the games that use it; we never
+//! link against it at build time (we aren't allowed to redistribute it, and it usually isn't even
+//! present on the build machine). Instead [`oodle()`] loads it from the dynamic loader's search
+//! path the first time it's needed, lazily, and binds the two symbols we call by name. Callers
+//! needing decode-only tooling on platforms without the library available should prefer
+//! [`crate::dcx::DcxMethod::Deflate`] paths.
+
+use std::io;
+use std::os::raw::{c_int, c_void};
+use std::sync::OnceLock;
+
+use libloading::{Library, Symbol};
+
+type OodleLzDecompressFn = unsafe extern "C" fn(
+    compBuf: *const u8,
+    compBufSize: usize,
+    rawBuf: *mut u8,
+    rawLen: usize,
+    fuzzSafe: c_int,
+    checkCRC: c_int,
+    verbosity: c_int,
+    decBufBase: *mut c_void,
+    decBufSize: usize,
+    fpCallback: *const c_void,
+    callbackUserData: *const c_void,
+    decoderMemory: *mut c_void,
+    decoderMemorySize: usize,
+    threadPhase: c_int,
+) -> c_int;
+
+type OodleLzCompressFn = unsafe extern "C" fn(
+    compressor: c_int,
+    rawBuf: *const u8,
+    rawLen: usize,
+    compBuf: *mut u8,
+    level: c_int,
+    pOptions: *const c_void,
+    dictionaryBase: *const c_void,
+    lrm: *const c_void,
+    scratchMem: *mut c_void,
+    scratchSize: usize,
+) -> c_int;
+
+/// Name the dynamic loader resolves on each platform; expected to be found next to the game's
+/// executable (Windows) or on `LD_LIBRARY_PATH` (the Linux ports that ship one).
+#[cfg(target_os = "windows")]
+const OODLE_LIBRARY_NAME: &str = "oo2core_9_win64.dll";
+#[cfg(not(target_os = "windows"))]
+const OODLE_LIBRARY_NAME: &str = "liboo2coreLinux64.so.9";
+
+struct OodleLibrary {
+    // Kept alive for as long as the resolved symbols below are used; never read directly.
+    _library: Library,
+    decompress: OodleLzDecompressFn,
+    compress: OodleLzCompressFn,
+}
+
+// The library handle and resolved function pointers are immutable after loading, so sharing them
+// across threads behind the `OnceLock` below is sound.
+unsafe impl Send for OodleLibrary {}
+unsafe impl Sync for OodleLibrary {}
+
+static OODLE: OnceLock<io::Result<OodleLibrary>> = OnceLock::new();
+
+fn oodle() -> io::Result<&'static OodleLibrary> {
+    OODLE
+        .get_or_init(|| unsafe {
+            let library = Library::new(OODLE_LIBRARY_NAME).map_err(|e| {
+                io::Error::other(format!(
+                    "failed to load {OODLE_LIBRARY_NAME} (expected on the dynamic loader's search \
+                     path): {e}"
+                ))
+            })?;
+            let decompress: Symbol<OodleLzDecompressFn> =
+                library.get(b"OodleLZ_Decompress\0").map_err(io::Error::other)?;
+            let compress: Symbol<OodleLzCompressFn> =
+                library.get(b"OodleLZ_Compress\0").map_err(io::Error::other)?;
+            let decompress = *decompress;
+            let compress = *compress;
+            Ok(OodleLibrary { _library: library, decompress, compress })
+        })
+        .as_ref()
+        .map_err(|e| io::Error::other(e.to_string()))
+}
+
+/// `OodleLZ_Compressor::Kraken`.
+const OODLELZ_COMPRESSOR_KRAKEN: c_int = 8;
+/// `OodleLZ_CompressionLevel::Optimal2`, a good size/speed tradeoff for one-shot repacking.
+const OODLELZ_COMPRESSIONLEVEL_OPTIMAL2: c_int = 6;
+
+/// Decompress a single Kraken-compressed block into a buffer of exactly `decompressed_size` bytes.
+pub fn decompress(compressed: &[u8], decompressed_size: usize) -> io::Result<Vec<u8>> {
+    let oodle = oodle()?;
+    let mut out = vec![0u8; decompressed_size];
+    let written = unsafe {
+        (oodle.decompress)(
+            compressed.as_ptr(),
+            compressed.len(),
+            out.as_mut_ptr(),
+            out.len(),
+            1,
+            1,
+            0,
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null(),
+            std::ptr::null(),
+            std::ptr::null_mut(),
+            0,
+            0,
+        )
+    };
+
+    if written as usize != decompressed_size {
+        return Err(io::Error::other(format!(
+            "OodleLZ_Decompress returned {written}, expected {decompressed_size}"
+        )));
+    }
+
+    Ok(out)
+}
+
+/// Compress `data` with Kraken at a size/speed tradeoff suitable for repacking game archives.
+pub fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let oodle = oodle()?;
+    // Oodle requires the output buffer to have some slack over the input size.
+    let mut out = vec![0u8; data.len() + 0x1000];
+    let written = unsafe {
+        (oodle.compress)(
+            OODLELZ_COMPRESSOR_KRAKEN,
+            data.as_ptr(),
+            data.len(),
+            out.as_mut_ptr(),
+            OODLELZ_COMPRESSIONLEVEL_OPTIMAL2,
+            std::ptr::null(),
+            std::ptr::null(),
+            std::ptr::null(),
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+
+    if written <= 0 {
+        return Err(io::Error::other("OodleLZ_Compress failed"));
+    }
+
+    out.truncate(written as usize);
+    Ok(out)
+}
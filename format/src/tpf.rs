@@ -0,0 +1,206 @@
+//! TPF: FromSoftware's texture bank format.
+//!
+//! A TPF bundles several DDS-like textures (each missing its own DDS header, sharing one
+//! header/format byte scheme instead) behind a small table of contents. [`Tpf::parse`] reads that
+//! table; [`TpfTexture::to_dds`] reconstructs a standalone DDS stream for each texture, and
+//! [`TpfTexture::to_png`] additionally decodes common block-compressed formats so the result is
+//! directly viewable.
+
+use std::io::{self, Cursor, Read, Seek};
+
+use crate::io_ext::{Endian, ReadFormatsExt};
+
+/// DXGI_FORMAT values this crate knows how to describe in a DDS header. The block-compressed ones
+/// (BC1/BC3/BC5/BC7) can also be decoded to RGBA8 by [`TpfTexture::to_png`]; `R8G8B8A8Unorm` is not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DxgiFormat {
+    Bc1Unorm,
+    Bc3Unorm,
+    Bc5Unorm,
+    Bc7Unorm,
+    R8G8B8A8Unorm,
+    Unknown(u32),
+}
+
+impl DxgiFormat {
+    /// DXGI_FORMAT numeric value, as written into `DDS_HEADER_DXT10::dxgiFormat`.
+    fn dxgi_value(self) -> u32 {
+        match self {
+            DxgiFormat::Bc1Unorm => 71,
+            DxgiFormat::Bc3Unorm => 77,
+            DxgiFormat::Bc5Unorm => 83,
+            DxgiFormat::Bc7Unorm => 98,
+            DxgiFormat::R8G8B8A8Unorm => 28,
+            DxgiFormat::Unknown(v) => v,
+        }
+    }
+
+    fn block_size(self) -> Option<usize> {
+        match self {
+            DxgiFormat::Bc1Unorm => Some(8),
+            DxgiFormat::Bc3Unorm | DxgiFormat::Bc5Unorm | DxgiFormat::Bc7Unorm => Some(16),
+            DxgiFormat::R8G8B8A8Unorm | DxgiFormat::Unknown(_) => None,
+        }
+    }
+
+    /// Maps the single-byte `format` field TPF stores per texture to a DXGI format.
+    ///
+    /// This table only covers the handful of formats named in [`DxgiFormat`]; unlisted
+    /// format bytes still round-trip through [`TpfTexture::to_dds`], they're just opaque there.
+    fn from_tpf_format_byte(format: u8) -> DxgiFormat {
+        match format {
+            0 | 1 | 5 | 100 => DxgiFormat::Bc1Unorm,
+            3 | 104 | 106 => DxgiFormat::Bc3Unorm,
+            36 | 107 => DxgiFormat::Bc5Unorm,
+            37 | 38 | 108 => DxgiFormat::Bc7Unorm,
+            2 | 102 => DxgiFormat::R8G8B8A8Unorm,
+            other => DxgiFormat::Unknown(other as u32),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TpfTexture {
+    pub name: String,
+    pub format: DxgiFormat,
+    pub width: u16,
+    pub height: u16,
+    pub mipmap_count: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tpf {
+    pub platform: u8,
+    pub flags: u8,
+    pub textures: Vec<TpfTexture>,
+}
+
+impl Tpf {
+    pub fn parse(data: &[u8]) -> io::Result<Self> {
+        let mut cursor = Cursor::new(data);
+
+        cursor.read_magic(b"TPF\0")?;
+        let _total_file_size = cursor.read_u32(Endian::Little)?;
+        let texture_count = cursor.read_u32(Endian::Little)? as usize;
+        let platform = cursor.read_u8()?;
+        let flags = cursor.read_u8()?;
+        let encoding = cursor.read_u8()?;
+        let _flag3 = cursor.read_u8()?;
+
+        let mut textures = Vec::with_capacity(texture_count);
+        for _ in 0..texture_count {
+            let file_offset = cursor.read_u32(Endian::Little)? as usize;
+            let file_size = cursor.read_u32(Endian::Little)? as usize;
+            let format_byte = cursor.read_u8()?;
+            let _texture_type = cursor.read_u8()?;
+            let mipmap_count = cursor.read_u8()?;
+            let _flags1 = cursor.read_u8()?;
+
+            if encoding == 3 {
+                let _file_size2 = cursor.read_u32(Endian::Little)?;
+            }
+
+            let width = cursor.read_u16(Endian::Little)?;
+            let height = cursor.read_u16(Endian::Little)?;
+            let _unk1 = cursor.read_u32(Endian::Little)?;
+            let name_offset = cursor.read_u32(Endian::Little)? as u64;
+            let _has_float_struct = cursor.read_u32(Endian::Little)?;
+
+            let return_pos = cursor.stream_position()?;
+            cursor.seek(io::SeekFrom::Start(name_offset))?;
+            let name = cursor.read_utf16_nul_terminated()?;
+            cursor.seek(io::SeekFrom::Start(return_pos))?;
+
+            let tex_data = data
+                .get(file_offset..file_offset + file_size)
+                .ok_or_else(|| io::Error::other("TPF texture data out of bounds"))?
+                .to_vec();
+
+            textures.push(TpfTexture {
+                name,
+                format: DxgiFormat::from_tpf_format_byte(format_byte),
+                width,
+                height,
+                mipmap_count,
+                data: tex_data,
+            });
+        }
+
+        Ok(Tpf { platform, flags, textures })
+    }
+}
+
+impl TpfTexture {
+    /// Reconstruct a standalone `.dds` byte stream: `DDS_HEADER` and `DDS_HEADER_DXT10` (written
+    /// for every format), followed by the already-compressed mip chain as stored in the TPF.
+    pub fn to_dds(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(128 + self.data.len());
+
+        out.extend_from_slice(b"DDS ");
+        out.extend_from_slice(&124u32.to_le_bytes()); // dwSize
+        let pitch_flags = 0x1 | 0x2 | 0x4 | 0x1000 | 0x20000; // CAPS|HEIGHT|WIDTH|PIXELFORMAT|MIPMAPCOUNT
+        out.extend_from_slice(&pitch_flags.to_le_bytes());
+        out.extend_from_slice(&(self.height as u32).to_le_bytes());
+        out.extend_from_slice(&(self.width as u32).to_le_bytes());
+
+        let linear_size = self
+            .format
+            .block_size()
+            .map(|block| ((self.width.max(1) as u32 + 3) / 4) * ((self.height.max(1) as u32 + 3) / 4) * block as u32)
+            .unwrap_or(self.width as u32 * 4);
+        out.extend_from_slice(&linear_size.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // dwDepth
+        out.extend_from_slice(&(self.mipmap_count.max(1) as u32).to_le_bytes());
+        out.extend_from_slice(&[0u8; 44]); // dwReserved1
+
+        // DDS_PIXELFORMAT: always "DX10" fourCC, real format lives in the DXT10 header.
+        out.extend_from_slice(&32u32.to_le_bytes()); // dwSize
+        out.extend_from_slice(&0x4u32.to_le_bytes()); // dwFlags = DDPF_FOURCC
+        out.extend_from_slice(b"DX10");
+        out.extend_from_slice(&[0u8; 20]); // RGB bit masks, unused under DX10
+
+        out.extend_from_slice(&0x1000u32.to_le_bytes()); // dwCaps = DDSCAPS_TEXTURE
+        out.extend_from_slice(&[0u8; 16]); // dwCaps2..dwReserved2
+
+        // DDS_HEADER_DXT10
+        out.extend_from_slice(&self.format.dxgi_value().to_le_bytes());
+        out.extend_from_slice(&3u32.to_le_bytes()); // resourceDimension = TEXTURE2D
+        out.extend_from_slice(&0u32.to_le_bytes()); // miscFlag
+        out.extend_from_slice(&1u32.to_le_bytes()); // arraySize
+        out.extend_from_slice(&0u32.to_le_bytes()); // miscFlags2
+
+        out.extend_from_slice(&self.data);
+        out
+    }
+
+    /// Decode the top mip level to RGBA8 and encode it as a PNG, for the block-compressed formats
+    /// this crate supports (BC1/BC3/BC5/BC7). Returns `None` for anything else.
+    pub fn to_png(&self) -> Option<Vec<u8>> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let mut pixels = vec![0u32; width * height];
+
+        match self.format {
+            DxgiFormat::Bc1Unorm => texture2ddecoder::decode_bc1(&self.data, width, height, &mut pixels).ok()?,
+            DxgiFormat::Bc3Unorm => texture2ddecoder::decode_bc3(&self.data, width, height, &mut pixels).ok()?,
+            DxgiFormat::Bc5Unorm => texture2ddecoder::decode_bc5(&self.data, width, height, &mut pixels).ok()?,
+            DxgiFormat::Bc7Unorm => texture2ddecoder::decode_bc7(&self.data, width, height, &mut pixels).ok()?,
+            DxgiFormat::R8G8B8A8Unorm | DxgiFormat::Unknown(_) => return None,
+        }
+
+        // `texture2ddecoder` packs pixels as 0xAARRGGBB; `image` wants RGBA byte order.
+        let mut rgba = Vec::with_capacity(pixels.len() * 4);
+        for pixel in pixels {
+            let [b, g, r, a] = pixel.to_le_bytes();
+            rgba.extend_from_slice(&[r, g, b, a]);
+        }
+
+        let image = image::RgbaImage::from_raw(width as u32, height as u32, rgba)?;
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .ok()?;
+        Some(png)
+    }
+}